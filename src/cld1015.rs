@@ -1,6 +1,37 @@
-use std::io::{self};
+use std::io::{self, BufRead, BufReader, Write};
 use visa_rs::prelude::*;
 
+use crate::scpi::ScpiDevice;
+
+pub mod temperature;
+
 pub fn io_to_vs_err(err: std::io::Error) -> visa_rs::Error {
     visa_rs::io_to_vs_err(err)
+}
+
+impl ScpiDevice for Instrument {
+    fn write_command(&mut self, cmd: &str) -> io::Result<()> {
+        let cmd_with_term = if cmd.ends_with('\n') {
+            cmd.to_string()
+        } else {
+            format!("{}\n", cmd)
+        };
+        self.write_all(cmd_with_term.as_bytes())
+    }
+
+    fn query(&mut self, cmd: &str) -> io::Result<String> {
+        self.write_command(cmd)?;
+
+        let mut response = String::new();
+        {
+            let mut reader = BufReader::new(&*self);
+            reader.read_line(&mut response)?;
+        }
+
+        Ok(response.trim().to_string())
+    }
+
+    fn check_errors(&mut self) -> io::Result<String> {
+        self.query("SYST:ERR?")
+    }
 }
\ No newline at end of file
@@ -0,0 +1,65 @@
+use std::io::Write;
+use std::time::{Duration, Instant};
+use visa_rs::prelude::*;
+
+use crate::cld1015::io_to_vs_err;
+use crate::pid::PidController;
+use crate::scpi::query_parsed;
+
+/// Enables the CLD1015's TEC output (OUTPut2).
+pub fn enable_tec(cld1015: &mut Instrument) -> visa_rs::Result<()> {
+    cld1015.write_all(b"OUTPut2:STATe 1\n").map_err(io_to_vs_err)
+}
+
+/// Disables the CLD1015's TEC output (OUTPut2).
+pub fn disable_tec(cld1015: &mut Instrument) -> visa_rs::Result<()> {
+    cld1015.write_all(b"OUTPut2:STATe 0\n").map_err(io_to_vs_err)
+}
+
+/// Sets the TEC temperature setpoint in degrees Celsius.
+pub fn set_temperature_setpoint(cld1015: &mut Instrument, setpoint_c: f64) -> visa_rs::Result<()> {
+    let cmd = format!("SOURce2:TEMPerature:SPOint {:.3}\n", setpoint_c);
+    cld1015.write_all(cmd.as_bytes()).map_err(io_to_vs_err)
+}
+
+/// Queries the measured TEC temperature in degrees Celsius.
+pub fn read_temperature(cld1015: &mut Instrument) -> visa_rs::Result<f64> {
+    query_parsed(cld1015, "MEASure:TEMPerature?").map_err(io_to_vs_err)
+}
+
+/// Polls the measured temperature, driving `pid` against the TEC current
+/// each `poll_interval`, until it settles within `tolerance_c` of
+/// `setpoint_c` or `timeout` elapses. Returns whether it settled in time.
+pub fn stabilize_temperature(
+    cld1015: &mut Instrument,
+    pid: &mut PidController,
+    setpoint_c: f64,
+    tolerance_c: f64,
+    poll_interval: Duration,
+    timeout: Duration,
+) -> visa_rs::Result<bool> {
+    let start = Instant::now();
+    let dt = poll_interval.as_secs_f64();
+
+    loop {
+        let measured_c = read_temperature(cld1015)?;
+        let error = setpoint_c - measured_c;
+
+        if error.abs() <= tolerance_c {
+            return Ok(true);
+        }
+
+        if start.elapsed() >= timeout {
+            return Ok(false);
+        }
+
+        let tec_current_a = pid.update(error, dt);
+        let cmd = format!(
+            "SOURce2:CURRent:LEVel:IMMediate:AMPLitude {:.6}\n",
+            tec_current_a
+        );
+        cld1015.write_all(cmd.as_bytes()).map_err(io_to_vs_err)?;
+
+        std::thread::sleep(poll_interval);
+    }
+}
@@ -1,13 +1,101 @@
 use std::fs::{self, File, create_dir_all};
-use std::io::{self, BufRead, BufReader, Write};
+use std::io::{self, Write};
 use std::path::Path;
 use std::time::Duration;
-use visa_rs::prelude::*;
+use visa_rs::Instrument;
 
 use crate::cld1015::io_to_vs_err;
+use crate::cld1015::temperature;
+use crate::mpm210h::{PowerUnit, MPM210H};
+use crate::osa::Osa;
+use crate::pid::PidController;
+use crate::scpi::{query_parsed, query_with_retry, ScpiDevice};
+use crate::telemetry::{TelemetryRecord, TelemetryServer};
+
+/// Sweep parameters configured at runtime (e.g. by the command REPL)
+/// instead of being baked in as compile-time constants.
+#[derive(Debug, Clone, Copy)]
+pub struct SweepConfig {
+    pub start_ma: f64,
+    pub stop_ma: f64,
+    pub step_ma: f64,
+    pub dwell_time_ms: u64,
+    pub center_wl_nm: f64,
+    pub span_wl_nm: f64,
+    pub current_limit_ma: f64,
+}
+
+impl Default for SweepConfig {
+    fn default() -> Self {
+        SweepConfig {
+            start_ma: 0.0,
+            stop_ma: 100.0,
+            step_ma: 0.1,
+            dwell_time_ms: 50,
+            center_wl_nm: 980.0,
+            span_wl_nm: 20.0,
+            current_limit_ma: 100.0,
+        }
+    }
+}
+
+impl SweepConfig {
+    pub fn set_start_ma(&mut self, v: f64) -> Result<(), String> {
+        if v < 0.0 {
+            return Err("start current must be >= 0 mA".to_string());
+        }
+        self.start_ma = v;
+        Ok(())
+    }
+
+    pub fn set_stop_ma(&mut self, v: f64) -> Result<(), String> {
+        if v <= 0.0 {
+            return Err("stop current must be > 0 mA".to_string());
+        }
+        self.stop_ma = v;
+        Ok(())
+    }
+
+    pub fn set_step_ma(&mut self, v: f64) -> Result<(), String> {
+        if v <= 0.0 {
+            return Err("step must be > 0 mA".to_string());
+        }
+        self.step_ma = v;
+        Ok(())
+    }
+
+    pub fn set_current_limit_ma(&mut self, v: f64) -> Result<(), String> {
+        if v <= 0.0 || v > 200.0 {
+            return Err("current limit must be between 0 and 200 mA".to_string());
+        }
+        self.current_limit_ma = v;
+        Ok(())
+    }
+
+    pub fn set_center_wl_nm(&mut self, v: f64) -> Result<(), String> {
+        if v < 600.0 || v > 1700.0 {
+            return Err("center wavelength must be between 600 and 1700 nm".to_string());
+        }
+        self.center_wl_nm = v;
+        Ok(())
+    }
+
+    pub fn set_span_wl_nm(&mut self, v: f64) -> Result<(), String> {
+        if v <= 0.0 || v > 1000.0 {
+            return Err("span must be between 0 and 1000 nm".to_string());
+        }
+        self.span_wl_nm = v;
+        Ok(())
+    }
+}
 
 /// Performs a current sweep with the CLD1015 laser diode controller
-/// and captures spectral data from the HP-70952B optical spectrum analyzer
+/// and captures spectral data from the HP-70952B optical spectrum analyzer.
+/// When `tag` is set (e.g. a temperature setpoint), it is appended to the
+/// summary CSV filename and the trace data directory so sweeps taken at
+/// different conditions don't overwrite each other. When `telemetry` is
+/// set, one JSON record per measured point is pushed to any connected
+/// telemetry client.
 pub fn run_current_sweep(
     cld1015: &mut Instrument,
     osa: &mut Instrument,
@@ -15,13 +103,25 @@ pub fn run_current_sweep(
     stop_ma: f64,
     step_ma: f64,
     dwell_time_ms: u64,
+    center_wl_nm: f64,
+    span_wl_nm: f64,
+    tag: Option<&str>,
+    mut telemetry: Option<&mut TelemetryServer>,
 ) -> visa_rs::Result<()> {
     // Create a CSV file to save summary results
-    let mut file = File::create("current_sweep_results.csv").unwrap();
+    let results_filename = match tag {
+        Some(t) => format!("current_sweep_results_{}.csv", t),
+        None => "current_sweep_results.csv".to_string(),
+    };
+    let mut file = File::create(&results_filename).unwrap();
     writeln!(file, "Current (mA),Peak Wavelength (nm),Peak Power (dBm)").unwrap();
-    
+
     // Create a directory to store trace data files
-    let trace_dir = "trace_data";
+    let trace_dir = match tag {
+        Some(t) => format!("trace_data_{}", t),
+        None => "trace_data".to_string(),
+    };
+    let trace_dir = trace_dir.as_str();
     create_dir_all(trace_dir).unwrap_or_else(|e| {
         println!("Warning: Failed to create trace data directory: {}", e);
     });
@@ -29,166 +129,468 @@ pub fn run_current_sweep(
     // Calculate number of points
     let num_points = ((stop_ma - start_ma) / step_ma).floor() as usize + 1;
     println!("Starting current sweep with {} points", num_points);
-    
+
     // Configure the OSA for measurements
-    osa.write_all(b"SNGLS;\n").map_err(io_to_vs_err)?; // Set to single sweep mode
-    osa.write_all(b"CENTERWL 980NM;SPANWL 20NM;\n").map_err(io_to_vs_err)?;
+    osa.write_command("SNGLS;").map_err(io_to_vs_err)?; // Set to single sweep mode
+    let center_span_cmd = format!("CENTERWL {}NM;SPANWL {}NM;", center_wl_nm, span_wl_nm);
+    osa.write_command(&center_span_cmd).map_err(io_to_vs_err)?;
 
-    
-    let center_wl = 980.0; // Center wavelength in nm
-    let span_wl = 20.0;    // Span in nm
-    let start_wl = center_wl - (span_wl / 2.0); // 970 nm
-    let stop_wl = center_wl + (span_wl / 2.0);  // 990 nm
+    let start_wl = center_wl_nm - (span_wl_nm / 2.0);
+    let stop_wl = center_wl_nm + (span_wl_nm / 2.0);
 
     // Get number of data points in trace
-    osa.write_all(b"MDS?;\n").map_err(io_to_vs_err)?;
-    let mut mds_response = String::new();
-    {
-        let mut reader = BufReader::new(&*osa);
-        reader.read_line(&mut mds_response).map_err(io_to_vs_err)?;
-    }
-    let num_trace_points = mds_response.trim().parse::<usize>().unwrap_or(800); // Default 800 if parsing fails
+    let num_trace_points = query_parsed::<_, usize>(osa, "MDS?;").map_err(io_to_vs_err)?;
     println!("Trace has {} data points", num_trace_points);
-    
+
     // Turn laser OFF
-    cld1015.write_all(b"OUTPut:STATe 0\n").map_err(io_to_vs_err)?;
+    cld1015.write_command("OUTPut:STATe 0").map_err(io_to_vs_err)?;
     println!("Laser turned OFF");
 
     // Wait for initial stabilization
     std::thread::sleep(Duration::from_millis(500));
-    
+
     // Turn laser ON
-    cld1015.write_all(b"OUTPut:STATe 1\n").map_err(io_to_vs_err)?;
+    cld1015.write_command("OUTPut:STATe 1").map_err(io_to_vs_err)?;
     println!("Laser turned ON");
-    
+
     // Wait for initial stabilization
     std::thread::sleep(Duration::from_millis(500));
-    
+
     // Perform the sweep
     for i in 0..num_points {
         let current_ma = start_ma + (i as f64 * step_ma);
-        
+
         // Convert mA to A for the device
         let current_a = current_ma / 1000.0;
-        
+
         // Set the current
-        let cmd = format!("SOURce:CURRent:LEVel:IMMediate:AMPLitude {:.6}\n", current_a);
-        cld1015.write_all(cmd.as_bytes()).map_err(io_to_vs_err)?;
-        
+        let cmd = format!("SOURce:CURRent:LEVel:IMMediate:AMPLitude {:.6}", current_a);
+        cld1015.write_command(&cmd).map_err(io_to_vs_err)?;
+
         println!("Set current to {:.2} mA", current_ma);
-        
-        // Wait for stabilization
-        std::thread::sleep(Duration::from_millis(dwell_time_ms));
+
+        // Wait for stabilization, ticking telemetry in small chunks so a
+        // connected client keeps getting frames between sweep points
+        const TICK_CHUNK_MS: u64 = 20;
+        let mut waited_ms = 0;
+        while waited_ms < dwell_time_ms {
+            let chunk_ms = TICK_CHUNK_MS.min(dwell_time_ms - waited_ms);
+            std::thread::sleep(Duration::from_millis(chunk_ms));
+            waited_ms += chunk_ms;
+            if let Some(t) = telemetry.as_deref_mut() {
+                t.tick();
+            }
+        }
         println!("Starting sweep");
-        
+
         // Trigger a new sweep on the OSA and confirm it's done before proceeding
-        osa.write_all(b"TS;DONE?;\n").map_err(io_to_vs_err)?; // Take sweep
-        let mut done_resp = String::new();
-        {
-            let mut reader = BufReader::new(&*osa);
-            reader.read_line(&mut done_resp).map_err(io_to_vs_err)?;
+        let done_resp = osa.query("TS;DONE?;").map_err(io_to_vs_err)?; // Take sweep
+        if done_resp != "1" {
+            println!("Warning: Sweep not confirmed complete. Response: {}", done_resp);
         }
-        if done_resp.trim() != "1" {
-            println!("Warning: Sweep not confirmed complete. Response: {}", done_resp.trim());
-        }
-        
+
         // Find peak
-        osa.write_all(b"MKPK HI;\n").map_err(io_to_vs_err)?; // Mark highest signal level
-        
-        // Get peak wavelength
-        osa.write_all(b"MKWL?;\n").map_err(io_to_vs_err)?;
-        let mut peak_wavelength = String::new();
-        {
-            let mut reader = BufReader::new(&*osa);
-            reader.read_line(&mut peak_wavelength).map_err(io_to_vs_err)?;
-        }
-        let peak_wavelength_nm = peak_wavelength.trim().parse::<f64>().unwrap_or(0.0) * 1.0e9; // Convert from meters to nm
-        
+        osa.write_command("MKPK HI;").map_err(io_to_vs_err)?; // Mark highest signal level
+
+        // Get peak wavelength (device reports meters; convert to nm), retrying
+        // a couple of times if the response is unparsable. Routed through
+        // the Osa adapter so the retry's error-queue check uses XERR? --
+        // the OSA's SYST:ERR? isn't one it understands.
+        let peak_wavelength_nm = query_with_retry::<_, f64>(&mut Osa(&mut *osa), "MKWL?;", 2)
+            .map(|wl_m| wl_m * 1.0e9)
+            .map_err(io_to_vs_err)?;
+
         // Get peak amplitude
-        osa.write_all(b"MKA?;\n").map_err(io_to_vs_err)?;
-        let mut peak_power = String::new();
-        {
-            let mut reader = BufReader::new(&*osa);
-            reader.read_line(&mut peak_power).map_err(io_to_vs_err)?;
-        }
-        let peak_power_dbm = peak_power.trim().parse::<f64>().unwrap_or(-100.0);
-        
+        let peak_power_dbm = query_with_retry::<_, f64>(&mut Osa(&mut *osa), "MKA?;", 2).map_err(io_to_vs_err)?;
+
         // Print measured values
         println!("  Peak Wavelength: {:.3} nm", peak_wavelength_nm);
         println!("  Peak Power: {:.2} dBm", peak_power_dbm);
-        
+
         // Write to results file
-        writeln!(file, "{:.2},{:.4},{:.2}", 
+        writeln!(file, "{:.2},{:.4},{:.2}",
                 current_ma, peak_wavelength_nm, peak_power_dbm).unwrap();
-        
+
+        if let Some(t) = telemetry.as_deref_mut() {
+            t.publish(TelemetryRecord {
+                current_ma,
+                peak_wl_nm: peak_wavelength_nm,
+                peak_power_dbm,
+            });
+        }
+
         // Fetch the entire trace data
         println!("Retrieving trace data...");
-        osa.write_all(b"TRA?;\n").map_err(io_to_vs_err)?;
-        
-        // Read trace data
-        let mut trace_data = String::new();
-        {
-            let mut reader = BufReader::new(&*osa);
-            reader.read_line(&mut trace_data).map_err(io_to_vs_err)?;
-        }
-        
+        let trace_data = osa.query("TRA?;").map_err(io_to_vs_err)?;
+
         // Calculate wavelength array for the x-axis
         let wavelength_step = (stop_wl - start_wl) / (num_trace_points as f64 - 1.0);
-        
+
         // Create trace data file
         let trace_filename = format!("{}/trace_{:.2}mA.csv", trace_dir, current_ma);
         let mut trace_file = File::create(&trace_filename).unwrap_or_else(|e| {
             println!("Warning: Failed to create trace file {}: {}", trace_filename, e);
             File::create("trace_data_fallback.csv").unwrap()
         });
-        
+
         // Write header to trace file
         writeln!(trace_file, "Wavelength (nm),Power (dBm)").unwrap();
-        
+
         // Parse and write trace data
-        let values: Vec<&str> = trace_data.trim().split(',').collect();
+        let values: Vec<&str> = trace_data.split(',').collect();
         for (j, value) in values.iter().enumerate() {
             if j < num_trace_points {
                 let wavelength = start_wl + (j as f64 * wavelength_step);
-                let power = value.parse::<f64>().unwrap_or(-100.0);
+                let power = value.parse::<f64>().map_err(|e| {
+                    io_to_vs_err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("failed to parse trace power value {:?}: {}", value, e),
+                    ))
+                })?;
                 writeln!(trace_file, "{:.4},{:.4}", wavelength, power).unwrap();
             }
         }
-        
+
         println!("  Trace data saved to {}", trace_filename);
     }
-    
+
     // Turn laser OFF
-    cld1015.write_all(b"OUTPut:STATe 0\n").map_err(io_to_vs_err)?;
+    cld1015.write_command("OUTPut:STATe 0").map_err(io_to_vs_err)?;
     println!("Laser turned OFF");
 
-    osa.write_all(b"SWEEP OFF;\n").map_err(io_to_vs_err)?; // Turn off
+    osa.write_command("SWEEP OFF;").map_err(io_to_vs_err)?; // Turn off
+
+    // Check for errors on CLD1015 via the uniform SCPI error queue
+    let cld_errors = cld1015.check_errors().map_err(io_to_vs_err)?;
+    println!("Final error check on CLD1015: {}", cld_errors);
+
+    // The HP-70952B predates SCPI error-queue conventions, so it keeps its
+    // own XERR? query instead of going through check_errors()
+    let osa_errors = osa.query("XERR?;").map_err(io_to_vs_err)?;
+    println!("Final error check on OSA: {}", osa_errors);
 
-    // Check for errors on CLD1015
-    cld1015.write_all(b"SYST:ERR?\n").map_err(io_to_vs_err)?;
-    
-    let mut response = String::new();
-    {
-        let mut reader = BufReader::new(&*cld1015);
-        reader.read_line(&mut response).map_err(io_to_vs_err)?;
-    }
-    
-    println!("Final error check on CLD1015: {}", response.trim());
-    
-    // Check for errors on OSA
-    osa.write_all(b"XERR?;\n").map_err(io_to_vs_err)?;
-    
-    let mut response = String::new();
-    {
-        let mut reader = BufReader::new(&*osa);
-        reader.read_line(&mut response).map_err(io_to_vs_err)?;
-    }
-    
-    println!("Final error check on OSA: {}", response.trim());
-    
     println!("Current sweep completed successfully");
-    println!("Summary results saved to current_sweep_results.csv");
+    println!("Summary results saved to {}", results_filename);
     println!("Trace data saved to {}/trace_*mA.csv files", trace_dir);
-    
+
+    Ok(())
+}
+
+/// Runs a 2D temperature/current sweep: for each temperature setpoint, waits
+/// for the TEC to settle within `tolerance_c` before sweeping current, with
+/// the resulting CSV/trace files tagged by the setpoint.
+pub fn run_temperature_sweep(
+    cld1015: &mut Instrument,
+    osa: &mut Instrument,
+    temperature_setpoints_c: &[f64],
+    tolerance_c: f64,
+    settle_timeout: Duration,
+    pid: &mut PidController,
+    start_ma: f64,
+    stop_ma: f64,
+    step_ma: f64,
+    dwell_time_ms: u64,
+    center_wl_nm: f64,
+    span_wl_nm: f64,
+    mut telemetry: Option<&mut TelemetryServer>,
+) -> visa_rs::Result<()> {
+    temperature::enable_tec(cld1015)?;
+
+    for &setpoint_c in temperature_setpoints_c {
+        println!("Setting TEC setpoint to {:.2} C", setpoint_c);
+        temperature::set_temperature_setpoint(cld1015, setpoint_c)?;
+
+        let settled = temperature::stabilize_temperature(
+            cld1015,
+            pid,
+            setpoint_c,
+            tolerance_c,
+            Duration::from_millis(500),
+            settle_timeout,
+        )?;
+        if !settled {
+            println!(
+                "Warning: temperature did not settle to {:.2} C within timeout, proceeding anyway",
+                setpoint_c
+            );
+        }
+
+        let tag = format!("{:.1}C", setpoint_c);
+        run_current_sweep(
+            cld1015,
+            osa,
+            start_ma,
+            stop_ma,
+            step_ma,
+            dwell_time_ms,
+            center_wl_nm,
+            span_wl_nm,
+            Some(&tag),
+            telemetry.as_deref_mut(),
+        )?;
+    }
+
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Holds a target optical power by closing a feedback loop between the
+/// networked MPM210H power meter and the CLD1015 current setpoint, using a
+/// discrete PID controller on the dBm error. Aborts safely (laser off) if
+/// the power diverges from target or the power meter fails to read for
+/// `max_consecutive_read_errors` samples in a row.
+pub fn run_constant_power(
+    cld1015: &mut Instrument,
+    power_meter: &mut MPM210H,
+    target_dbm: f64,
+    i0_a: f64,
+    i_min_a: f64,
+    i_limit_a: f64,
+    pid: &mut PidController,
+    tolerance_dbm: f64,
+    divergence_dbm: f64,
+    poll_interval: Duration,
+    max_iterations: u64,
+    max_consecutive_read_errors: u32,
+) -> visa_rs::Result<()> {
+    // The error loop below compares target_dbm directly against
+    // read_power()'s return, so the meter must be in dBm mode.
+    power_meter.set_power_unit(PowerUnit::DBm).map_err(io_to_vs_err)?;
+
+    // Turn laser OFF before changing the operating point
+    cld1015.write_command("OUTPut:STATe 0").map_err(io_to_vs_err)?;
+    std::thread::sleep(Duration::from_millis(500));
+
+    let initial_current_a = i0_a.clamp(i_min_a, i_limit_a);
+    let cmd = format!("SOURce:CURRent:LEVel:IMMediate:AMPLitude {:.6}", initial_current_a);
+    cld1015.write_command(&cmd).map_err(io_to_vs_err)?;
+
+    // Turn laser ON
+    cld1015.write_command("OUTPut:STATe 1").map_err(io_to_vs_err)?;
+    println!("Laser turned ON, targeting {:.2} dBm", target_dbm);
+    std::thread::sleep(Duration::from_millis(500));
+
+    let mut consecutive_read_errors = 0;
+    let dt = poll_interval.as_secs_f64();
+
+    for iteration in 0..max_iterations {
+        let measured_dbm = match power_meter.read_power() {
+            Ok(p) => {
+                consecutive_read_errors = 0;
+                p
+            }
+            Err(e) => {
+                consecutive_read_errors += 1;
+                println!(
+                    "Warning: failed to read power ({}/{} consecutive): {}",
+                    consecutive_read_errors, max_consecutive_read_errors, e
+                );
+                if consecutive_read_errors >= max_consecutive_read_errors {
+                    cld1015.write_command("OUTPut:STATe 0").map_err(io_to_vs_err)?;
+                    return Err(io_to_vs_err(io::Error::new(
+                        io::ErrorKind::TimedOut,
+                        "power meter read failed too many times in a row, laser turned off",
+                    )));
+                }
+                std::thread::sleep(poll_interval);
+                continue;
+            }
+        };
+
+        let error = target_dbm - measured_dbm;
+
+        if error.abs() > divergence_dbm {
+            cld1015.write_command("OUTPut:STATe 0").map_err(io_to_vs_err)?;
+            return Err(io_to_vs_err(io::Error::new(
+                io::ErrorKind::Other,
+                format!(
+                    "optical power diverged from target ({:.2} dBm vs {:.2} dBm target), laser turned off",
+                    measured_dbm, target_dbm
+                ),
+            )));
+        }
+
+        if error.abs() <= tolerance_dbm {
+            println!(
+                "Converged: {:.2} dBm (target {:.2} dBm) after {} iterations",
+                measured_dbm, target_dbm, iteration
+            );
+            return Ok(());
+        }
+
+        // PID operates on the offset from i0_a so the clamp below and the
+        // controller's own out_min/out_max (configured as i_min_a - i0_a,
+        // i_limit_a - i0_a) agree on when the output has saturated.
+        let delta_a = pid.update(error, dt);
+        let current_a = (i0_a + delta_a).clamp(i_min_a, i_limit_a);
+
+        let cmd = format!("SOURce:CURRent:LEVel:IMMediate:AMPLitude {:.6}", current_a);
+        cld1015.write_command(&cmd).map_err(io_to_vs_err)?;
+
+        std::thread::sleep(poll_interval);
+    }
+
+    println!("Warning: constant-power loop reached max iterations without converging");
+    Ok(())
+}
+
+/// Runs a full LIV characterization: at each current step, records the
+/// CLD1015 forward voltage and the OSA peak alongside wavelength-calibrated
+/// optical power from the MPM210H, then fits the above-threshold L-I region
+/// to report threshold current and slope efficiency in a summary file.
+pub fn run_liv_sweep(
+    cld1015: &mut Instrument,
+    osa: &mut Instrument,
+    power_meter: &mut MPM210H,
+    nominal_wavelength_nm: f64,
+    start_ma: f64,
+    stop_ma: f64,
+    step_ma: f64,
+    dwell_time_ms: u64,
+    center_wl_nm: f64,
+    span_wl_nm: f64,
+    tag: Option<&str>,
+) -> visa_rs::Result<()> {
+    power_meter
+        .set_wavelength(nominal_wavelength_nm)
+        .map_err(io_to_vs_err)?;
+    power_meter.zero().map_err(io_to_vs_err)?;
+    // The CSV's "Power (mW)" column and the threshold/slope fit derived
+    // from it both assume linear mW readings, not dBm.
+    power_meter.set_power_unit(PowerUnit::MW).map_err(io_to_vs_err)?;
+
+    let results_filename = match tag {
+        Some(t) => format!("liv_sweep_results_{}.csv", t),
+        None => "liv_sweep_results.csv".to_string(),
+    };
+    let mut file = File::create(&results_filename).unwrap();
+    writeln!(
+        file,
+        "Current (mA),Voltage (V),Power (mW),Peak Wavelength (nm),Peak Power (dBm)"
+    )
+    .unwrap();
+
+    let num_points = ((stop_ma - start_ma) / step_ma).floor() as usize + 1;
+    println!("Starting LIV sweep with {} points", num_points);
+
+    osa.write_command("SNGLS;").map_err(io_to_vs_err)?;
+    let center_span_cmd = format!("CENTERWL {}NM;SPANWL {}NM;", center_wl_nm, span_wl_nm);
+    osa.write_command(&center_span_cmd).map_err(io_to_vs_err)?;
+
+    // Turn laser OFF then ON so every sweep starts from the same state
+    cld1015.write_command("OUTPut:STATe 0").map_err(io_to_vs_err)?;
+    std::thread::sleep(Duration::from_millis(500));
+    cld1015.write_command("OUTPut:STATe 1").map_err(io_to_vs_err)?;
+    println!("Laser turned ON");
+    std::thread::sleep(Duration::from_millis(500));
+
+    // (current_ma, power_mw) pairs, kept for the threshold/slope fit below
+    let mut li_points: Vec<(f64, f64)> = Vec::with_capacity(num_points);
+
+    for i in 0..num_points {
+        let current_ma = start_ma + (i as f64 * step_ma);
+        let current_a = current_ma / 1000.0;
+
+        let cmd = format!("SOURce:CURRent:LEVel:IMMediate:AMPLitude {:.6}", current_a);
+        cld1015.write_command(&cmd).map_err(io_to_vs_err)?;
+
+        std::thread::sleep(Duration::from_millis(dwell_time_ms));
+
+        let voltage_v = query_with_retry::<_, f64>(cld1015, "MEASure:VOLTage?", 2).map_err(io_to_vs_err)?;
+        let power_mw = power_meter.read_power().map_err(io_to_vs_err)?;
+
+        let done_resp = osa.query("TS;DONE?;").map_err(io_to_vs_err)?;
+        if done_resp != "1" {
+            println!("Warning: Sweep not confirmed complete. Response: {}", done_resp);
+        }
+        osa.write_command("MKPK HI;").map_err(io_to_vs_err)?;
+
+        let peak_wavelength_nm = query_with_retry::<_, f64>(&mut Osa(&mut *osa), "MKWL?;", 2)
+            .map(|wl_m| wl_m * 1.0e9)
+            .map_err(io_to_vs_err)?;
+        let peak_power_dbm = query_with_retry::<_, f64>(&mut Osa(&mut *osa), "MKA?;", 2).map_err(io_to_vs_err)?;
+
+        println!(
+            "  {:.2} mA: V={:.3} V, P={:.4} mW, peak {:.3} nm @ {:.2} dBm",
+            current_ma, voltage_v, power_mw, peak_wavelength_nm, peak_power_dbm
+        );
+
+        writeln!(
+            file,
+            "{:.2},{:.4},{:.4},{:.4},{:.2}",
+            current_ma, voltage_v, power_mw, peak_wavelength_nm, peak_power_dbm
+        )
+        .unwrap();
+
+        li_points.push((current_ma, power_mw));
+    }
+
+    cld1015.write_command("OUTPut:STATe 0").map_err(io_to_vs_err)?;
+    println!("Laser turned OFF");
+
+    let summary_filename = match tag {
+        Some(t) => format!("liv_summary_{}.txt", t),
+        None => "liv_summary.txt".to_string(),
+    };
+    match fit_threshold_and_slope_efficiency(&li_points) {
+        Some((threshold_ma, slope_mw_per_ma)) => {
+            let mut summary = File::create(&summary_filename).unwrap();
+            writeln!(summary, "Threshold current (mA): {:.3}", threshold_ma).unwrap();
+            writeln!(summary, "Slope efficiency (mW/mA): {:.4}", slope_mw_per_ma).unwrap();
+            println!(
+                "Threshold current: {:.3} mA, slope efficiency: {:.4} mW/mA (saved to {})",
+                threshold_ma, slope_mw_per_ma, summary_filename
+            );
+        }
+        None => {
+            println!(
+                "Warning: could not determine a clean above-threshold region; skipping threshold/slope summary"
+            );
+        }
+    }
+
+    println!("LIV sweep completed successfully");
+    println!("Summary results saved to {}", results_filename);
+
+    Ok(())
+}
+
+/// Fits a line to the above-threshold region of an L-I curve (points at or
+/// above half the observed max power) and returns
+/// `(threshold_current_ma, slope_efficiency_mw_per_ma)`: the fit's
+/// intersection with the current axis, and its slope. Returns `None` if
+/// there isn't enough of an above-threshold region to fit.
+fn fit_threshold_and_slope_efficiency(points: &[(f64, f64)]) -> Option<(f64, f64)> {
+    let max_power = points.iter().map(|(_, p)| *p).fold(f64::MIN, f64::max);
+    if max_power <= 0.0 {
+        return None;
+    }
+
+    let region: Vec<(f64, f64)> = points
+        .iter()
+        .copied()
+        .filter(|(_, p)| *p >= 0.5 * max_power)
+        .collect();
+    if region.len() < 2 {
+        return None;
+    }
+
+    let n = region.len() as f64;
+    let sum_x: f64 = region.iter().map(|(x, _)| x).sum();
+    let sum_y: f64 = region.iter().map(|(_, y)| y).sum();
+    let sum_xy: f64 = region.iter().map(|(x, y)| x * y).sum();
+    let sum_xx: f64 = region.iter().map(|(x, _)| x * x).sum();
+
+    let denom = n * sum_xx - sum_x * sum_x;
+    if denom.abs() < f64::EPSILON {
+        return None;
+    }
+
+    let slope = (n * sum_xy - sum_x * sum_y) / denom;
+    if slope.abs() < f64::EPSILON {
+        return None;
+    }
+    let intercept = (sum_y - slope * sum_x) / n;
+
+    let threshold_current_ma = -intercept / slope;
+    Some((threshold_current_ma, slope))
+}
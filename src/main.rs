@@ -1,6 +1,12 @@
 #![allow(unused)]
 mod cld1015;
 mod experiment;
+mod mpm210h;
+mod osa;
+mod pid;
+mod repl;
+mod scpi;
+mod telemetry;
 
 use std::ffi::CString;
 use std::io::{self, BufRead, BufReader, Write};
@@ -8,6 +14,8 @@ use std::time::Duration;
 use visa_rs::prelude::*;
 
 use cld1015::io_to_vs_err;
+use repl::Session;
+use telemetry::TelemetryServer;
 
 fn main() -> visa_rs::Result<()> {
     // Initialize the VISA resource manager
@@ -80,16 +88,35 @@ fn main() -> visa_rs::Result<()> {
     // Set the CLD1015 to operate in Constant Current mode
     cld1015.write_all(b"SOURce:FUNCtion:MODE CURRent\n").map_err(io_to_vs_err)?;
 
-    // Set current limit to a safe value
+    // Set current limit to a safe default; `set limit <mA>` can change it later
     cld1015.write_all(b"SOURce:CURRent:LIMit:AMPLitude 100MA\n").map_err(io_to_vs_err)?;
-    
-    // Configure and run the current sweep
-    let start_ma = 0.0;     // Start at 0 mA
-    let stop_ma = 100.0;    // End at 100 mA
-    let step_ma = 0.1;      // 1 mA steps
-    let dwell_time_ms = 50; // 100ms stabilization delay
-    
-    experiment::run_current_sweep(&mut cld1015, &mut osa, start_ma, stop_ma, step_ma, dwell_time_ms)?;
-    
+
+    // Stream live telemetry to any connected client; proceed without it if
+    // the port can't be bound
+    let telemetry_addr = "0.0.0.0:9100";
+    let mut telemetry_server = match TelemetryServer::bind(telemetry_addr, Duration::from_millis(500)) {
+        Ok(server) => {
+            println!("Telemetry server listening on {}", telemetry_addr);
+            Some(server)
+        }
+        Err(e) => {
+            println!("Warning: failed to start telemetry server: {}", e);
+            None
+        }
+    };
+
+    let mut session = Session::new(&mut cld1015, &mut osa);
+    session.telemetry = telemetry_server.as_mut();
+
+    // Drive the session from stdin so sweeps can be configured and
+    // triggered without a rebuild (`set start 0`, `center 980`, `sweep`,
+    // `connect powermeter <addr>`, `power <target>`, `tempsweep
+    // <c1,c2,...>`, `off`, ...). `repl::run_repl` is generic over any
+    // `BufRead`/`Write` pair, so the same loop can drive a TCP connection
+    // instead of stdin if the binary is wired up as a socket server.
+    println!("Ready. Type commands (e.g. 'set start 0', 'connect powermeter <host:port>', 'sweep', 'off'):");
+    let stdin = io::stdin();
+    repl::run_repl(stdin.lock(), io::stdout(), &mut session).map_err(io_to_vs_err)?;
+
     Ok(())
 }
\ No newline at end of file
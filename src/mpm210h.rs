@@ -2,6 +2,8 @@ use std::io::{self, BufRead, BufReader, Write};
 use std::net::{TcpStream, ToSocketAddrs};
 use std::time::Duration;
 
+use crate::scpi::ScpiDevice;
+
 #[derive(Debug, Clone, Copy)]
 pub enum PowerUnit {
     DBm,
@@ -32,12 +34,12 @@ impl MPM210H {
         stream.set_read_timeout(Some(Duration::from_secs(5)))?;
         stream.set_write_timeout(Some(Duration::from_secs(5)))?;
         
-        let mpm = MPM210H {
+        let mut mpm = MPM210H {
             stream,
             module: 0,
             port: 1,
         };
-        
+
         // Check connection by querying device identity
         mpm.query("*IDN?")?;
         
@@ -148,39 +150,42 @@ impl MPM210H {
         }
     }
     
+}
+
+impl ScpiDevice for MPM210H {
     /// Write a command to the device
     fn write_command(&mut self, cmd: &str) -> io::Result<()> {
         // Add LF termination and write the command
         let cmd_with_term = format!("{}\n", cmd);
         self.stream.write_all(cmd_with_term.as_bytes())?;
-        
+
         // MPM-210H needs a 10ms delay after each command
         std::thread::sleep(Duration::from_millis(10));
-        
+
         Ok(())
     }
-    
+
     /// Send a query and read the response
-    fn query(&self, cmd: &str) -> io::Result<String> {
+    fn query(&mut self, cmd: &str) -> io::Result<String> {
         let mut s = self.stream.try_clone()?;
-        
+
         // Add LF termination and write the command
         let cmd_with_term = format!("{}\n", cmd);
         s.write_all(cmd_with_term.as_bytes())?;
-        
+
         // MPM-210H needs a 10ms delay after each command
         std::thread::sleep(Duration::from_millis(10));
-        
+
         // Read the response
         let mut reader = BufReader::new(s);
         let mut response = String::new();
         reader.read_line(&mut response)?;
-        
-        Ok(response)
+
+        Ok(response.trim().to_string())
     }
-    
+
     /// Check for any errors
-    pub fn check_errors(&self) -> io::Result<String> {
+    fn check_errors(&mut self) -> io::Result<String> {
         self.query("ERR?")
     }
 }
\ No newline at end of file
@@ -0,0 +1,26 @@
+use std::io;
+use visa_rs::Instrument;
+
+use crate::scpi::ScpiDevice;
+
+/// Adapts an `Instrument` driving the HP-70952B OSA so `check_errors` goes
+/// through this device's own `XERR?` query instead of the generic
+/// `ScpiDevice for Instrument` impl's `SYST:ERR?`. The HP-70952B predates
+/// SCPI error-queue conventions and won't answer `SYST:ERR?` the way
+/// `is_no_error` expects, which otherwise makes `query_with_retry` treat
+/// every good OSA reading as a failure.
+pub struct Osa<'a>(pub &'a mut Instrument);
+
+impl<'a> ScpiDevice for Osa<'a> {
+    fn write_command(&mut self, cmd: &str) -> io::Result<()> {
+        self.0.write_command(cmd)
+    }
+
+    fn query(&mut self, cmd: &str) -> io::Result<String> {
+        self.0.query(cmd)
+    }
+
+    fn check_errors(&mut self) -> io::Result<String> {
+        self.0.query("XERR?;")
+    }
+}
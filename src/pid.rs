@@ -0,0 +1,49 @@
+/// Generic discrete PID controller with anti-windup, shared by any feedback
+/// loop that drives an instrument setpoint off a measured process variable
+/// (e.g. TEC current off temperature error, or laser current off optical
+/// power error).
+pub struct PidController {
+    kp: f64,
+    ki: f64,
+    kd: f64,
+    out_min: f64,
+    out_max: f64,
+    integral: f64,
+    prev_error: Option<f64>,
+}
+
+impl PidController {
+    pub fn new(kp: f64, ki: f64, kd: f64, out_min: f64, out_max: f64) -> Self {
+        PidController {
+            kp,
+            ki,
+            kd,
+            out_min,
+            out_max,
+            integral: 0.0,
+            prev_error: None,
+        }
+    }
+
+    /// Computes one PID step given the current error and elapsed time since
+    /// the previous step, clamping the output to the configured range and
+    /// freezing the integral accumulator whenever the output saturates so
+    /// the integrator cannot keep growing while pinned.
+    pub fn update(&mut self, error: f64, dt: f64) -> f64 {
+        let derivative = match self.prev_error {
+            Some(prev) if dt > 0.0 => (error - prev) / dt,
+            _ => 0.0,
+        };
+
+        let candidate_integral = self.integral + self.ki * error * dt;
+        let unclamped = self.kp * error + candidate_integral + self.kd * derivative;
+        let output = unclamped.clamp(self.out_min, self.out_max);
+
+        if output == unclamped {
+            self.integral = candidate_integral;
+        }
+
+        self.prev_error = Some(error);
+        output
+    }
+}
@@ -0,0 +1,258 @@
+use std::io::{BufRead, Write};
+use std::time::Duration;
+use visa_rs::Instrument;
+
+use crate::experiment::{self, SweepConfig};
+use crate::mpm210h::MPM210H;
+use crate::pid::PidController;
+use crate::scpi::ScpiDevice;
+use crate::telemetry::TelemetryServer;
+
+/// A parsed REPL command.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    SetStart(f64),
+    SetStop(f64),
+    SetStep(f64),
+    SetLimit(f64),
+    Center(f64),
+    Span(f64),
+    Sweep,
+    Power(f64),
+    Liv(f64),
+    TempSweep(Vec<f64>),
+    ConnectPowerMeter(String),
+    Off,
+}
+
+/// Parses one line of input into a `Command`, or an error message suitable
+/// for echoing back to the caller.
+pub fn parse_command(line: &str) -> Result<Command, String> {
+    let tokens: Vec<&str> = line.trim().split_whitespace().collect();
+    match tokens.as_slice() {
+        ["set", "start", v] => parse_f64(v).map(Command::SetStart),
+        ["set", "stop", v] => parse_f64(v).map(Command::SetStop),
+        ["set", "step", v] => parse_f64(v).map(Command::SetStep),
+        ["set", "limit", v] => parse_ma(v).map(Command::SetLimit),
+        ["center", v] => parse_f64(v).map(Command::Center),
+        ["span", v] => parse_f64(v).map(Command::Span),
+        ["sweep"] => Ok(Command::Sweep),
+        ["power", v] => parse_f64(v).map(Command::Power),
+        ["liv", v] => parse_f64(v).map(Command::Liv),
+        ["tempsweep", v] => parse_temp_list(v).map(Command::TempSweep),
+        ["connect", "powermeter", addr] => Ok(Command::ConnectPowerMeter(addr.to_string())),
+        ["off"] => Ok(Command::Off),
+        [] => Err("empty command".to_string()),
+        _ => Err(format!("unrecognized command: {}", line.trim())),
+    }
+}
+
+fn parse_f64(v: &str) -> Result<f64, String> {
+    v.parse::<f64>().map_err(|e| format!("invalid number '{}': {}", v, e))
+}
+
+fn parse_ma(v: &str) -> Result<f64, String> {
+    let lower = v.to_ascii_lowercase();
+    let numeric = lower.strip_suffix("ma").unwrap_or(&lower);
+    parse_f64(numeric)
+}
+
+/// Parses a comma-separated list of temperature setpoints, e.g. `"20,25,30"`.
+fn parse_temp_list(v: &str) -> Result<Vec<f64>, String> {
+    let setpoints: Vec<f64> = v
+        .split(',')
+        .map(parse_f64)
+        .collect::<Result<_, _>>()?;
+    if setpoints.is_empty() {
+        return Err("tempsweep requires at least one setpoint".to_string());
+    }
+    Ok(setpoints)
+}
+
+/// Owns the instrument handles and runtime sweep configuration that a REPL
+/// session dispatches commands against, making the binary scriptable
+/// without a rebuild.
+pub struct Session<'a> {
+    pub cld1015: &'a mut Instrument,
+    pub osa: &'a mut Instrument,
+    pub power_meter: Option<MPM210H>,
+    pub telemetry: Option<&'a mut TelemetryServer>,
+    pub config: SweepConfig,
+}
+
+impl<'a> Session<'a> {
+    pub fn new(cld1015: &'a mut Instrument, osa: &'a mut Instrument) -> Self {
+        Session {
+            cld1015,
+            osa,
+            power_meter: None,
+            telemetry: None,
+            config: SweepConfig::default(),
+        }
+    }
+
+    /// Executes a single parsed command, returning a short status message
+    /// on success.
+    pub fn dispatch(&mut self, command: Command) -> Result<String, String> {
+        match command {
+            Command::SetStart(v) => self.config.set_start_ma(v).map(|_| format!("start = {} mA", v)),
+            Command::SetStop(v) => self.config.set_stop_ma(v).map(|_| format!("stop = {} mA", v)),
+            Command::SetStep(v) => self.config.set_step_ma(v).map(|_| format!("step = {} mA", v)),
+            Command::SetLimit(v) => self.set_current_limit(v),
+            Command::Center(v) => self.config.set_center_wl_nm(v).map(|_| format!("center = {} nm", v)),
+            Command::Span(v) => self.config.set_span_wl_nm(v).map(|_| format!("span = {} nm", v)),
+            Command::Sweep => self.run_sweep(),
+            Command::Power(target_dbm) => self.run_power(target_dbm),
+            Command::Liv(nominal_wavelength_nm) => self.run_liv(nominal_wavelength_nm),
+            Command::TempSweep(setpoints) => self.run_tempsweep(&setpoints),
+            Command::ConnectPowerMeter(addr) => self.connect_power_meter(&addr),
+            Command::Off => self.turn_off(),
+        }
+    }
+
+    fn set_current_limit(&mut self, v: f64) -> Result<String, String> {
+        self.config.set_current_limit_ma(v)?;
+        let cmd = format!("SOURce:CURRent:LIMit:AMPLitude {:.3}MA", v);
+        self.cld1015
+            .write_command(&cmd)
+            .map_err(|e| format!("failed to set current limit: {}", e))?;
+        Ok(format!("current limit = {} mA", v))
+    }
+
+    fn run_sweep(&mut self) -> Result<String, String> {
+        experiment::run_current_sweep(
+            self.cld1015,
+            self.osa,
+            self.config.start_ma,
+            self.config.stop_ma,
+            self.config.step_ma,
+            self.config.dwell_time_ms,
+            self.config.center_wl_nm,
+            self.config.span_wl_nm,
+            None,
+            self.telemetry.as_deref_mut(),
+        )
+        .map(|_| "sweep complete".to_string())
+        .map_err(|e| format!("sweep failed: {}", e))
+    }
+
+    fn run_power(&mut self, target_dbm: f64) -> Result<String, String> {
+        let power_meter = self
+            .power_meter
+            .as_mut()
+            .ok_or_else(|| "no power meter connected".to_string())?;
+
+        let i_min_a = 0.0;
+        let i_limit_a = self.config.current_limit_ma / 1000.0;
+        let i0_a = (self.config.start_ma + self.config.stop_ma) / 2.0 / 1000.0;
+        let mut pid = PidController::new(0.002, 0.0005, 0.0, i_min_a - i0_a, i_limit_a - i0_a);
+
+        experiment::run_constant_power(
+            self.cld1015,
+            power_meter,
+            target_dbm,
+            i0_a,
+            i_min_a,
+            i_limit_a,
+            &mut pid,
+            0.1,
+            10.0,
+            Duration::from_millis(100),
+            200,
+            5,
+        )
+        .map(|_| format!("targeting {} dBm", target_dbm))
+        .map_err(|e| format!("constant-power mode failed: {}", e))
+    }
+
+    fn run_liv(&mut self, nominal_wavelength_nm: f64) -> Result<String, String> {
+        let power_meter = self
+            .power_meter
+            .as_mut()
+            .ok_or_else(|| "no power meter connected".to_string())?;
+
+        experiment::run_liv_sweep(
+            self.cld1015,
+            self.osa,
+            power_meter,
+            nominal_wavelength_nm,
+            self.config.start_ma,
+            self.config.stop_ma,
+            self.config.step_ma,
+            self.config.dwell_time_ms,
+            self.config.center_wl_nm,
+            self.config.span_wl_nm,
+            None,
+        )
+        .map(|_| "LIV sweep complete".to_string())
+        .map_err(|e| format!("LIV sweep failed: {}", e))
+    }
+
+    /// Connects the MPM210H power meter used by `power`/`liv`, replacing any
+    /// previously connected one.
+    fn connect_power_meter(&mut self, addr: &str) -> Result<String, String> {
+        let power_meter =
+            MPM210H::connect(addr).map_err(|e| format!("failed to connect power meter at {}: {}", addr, e))?;
+        self.power_meter = Some(power_meter);
+        Ok(format!("power meter connected at {}", addr))
+    }
+
+    fn run_tempsweep(&mut self, setpoints: &[f64]) -> Result<String, String> {
+        let mut pid = PidController::new(0.5, 0.05, 0.1, -0.5, 0.5);
+
+        experiment::run_temperature_sweep(
+            self.cld1015,
+            self.osa,
+            setpoints,
+            0.1,
+            Duration::from_secs(120),
+            &mut pid,
+            self.config.start_ma,
+            self.config.stop_ma,
+            self.config.step_ma,
+            self.config.dwell_time_ms,
+            self.config.center_wl_nm,
+            self.config.span_wl_nm,
+            self.telemetry.as_deref_mut(),
+        )
+        .map(|_| format!("temperature sweep complete over {} setpoint(s)", setpoints.len()))
+        .map_err(|e| format!("temperature sweep failed: {}", e))
+    }
+
+    fn turn_off(&mut self) -> Result<String, String> {
+        self.cld1015
+            .write_command("OUTPut:STATe 0")
+            .map_err(|e| format!("failed to turn laser off: {}", e))?;
+        Ok("laser off".to_string())
+    }
+}
+
+/// Reads commands line-by-line from `input`, dispatches each to `session`,
+/// and writes an "OK ..." or "ERR ..." response line to `output` for each.
+/// Returns once `input` reaches EOF, so the same loop drives both a stdin
+/// session and a per-connection TCP session.
+pub fn run_repl<R: BufRead, W: Write>(
+    input: R,
+    mut output: W,
+    session: &mut Session,
+) -> std::io::Result<()> {
+    for line in input.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let response = match parse_command(&line) {
+            Ok(command) => match session.dispatch(command) {
+                Ok(msg) => format!("OK {}\n", msg),
+                Err(e) => format!("ERR {}\n", e),
+            },
+            Err(e) => format!("ERR {}\n", e),
+        };
+
+        output.write_all(response.as_bytes())?;
+        output.flush()?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,83 @@
+use std::io;
+use std::str::FromStr;
+
+/// Common interface for SCPI-speaking instruments, whether reached over
+/// VISA/GPIB (`visa_rs::Instrument`) or a raw TCP socket (`MPM210H`), so
+/// callers don't have to hand-roll the write/read_line dance per transport.
+pub trait ScpiDevice {
+    /// Sends a command with no response expected.
+    fn write_command(&mut self, cmd: &str) -> io::Result<()>;
+
+    /// Sends a command and returns the trimmed response line.
+    fn query(&mut self, cmd: &str) -> io::Result<String>;
+
+    /// Queries the device's error queue.
+    fn check_errors(&mut self) -> io::Result<String>;
+}
+
+/// Issues `cmd` and parses the response as `T`, returning a typed error
+/// instead of silently substituting a sentinel value on a bad parse.
+pub fn query_parsed<D, T>(device: &mut D, cmd: &str) -> io::Result<T>
+where
+    D: ScpiDevice + ?Sized,
+    T: FromStr,
+    T::Err: std::fmt::Display,
+{
+    let response = device.query(cmd)?;
+    response.trim().parse::<T>().map_err(|e| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "failed to parse response {:?} to '{}' as {}: {}",
+                response,
+                cmd,
+                std::any::type_name::<T>(),
+                e
+            ),
+        )
+    })
+}
+
+/// Re-issues `cmd` up to `retries` additional times if the response fails to
+/// parse or the device's error queue is non-empty afterwards.
+pub fn query_with_retry<D, T>(device: &mut D, cmd: &str, retries: u32) -> io::Result<T>
+where
+    D: ScpiDevice + ?Sized,
+    T: FromStr,
+    T::Err: std::fmt::Display,
+{
+    let mut last_err = None;
+
+    for attempt in 0..=retries {
+        match query_parsed::<D, T>(device, cmd) {
+            Ok(value) => match device.check_errors() {
+                Ok(err_resp) if is_no_error(&err_resp) => return Ok(value),
+                Ok(err_resp) => {
+                    last_err = Some(io::Error::new(
+                        io::ErrorKind::Other,
+                        format!("device reported error after '{}': {}", cmd, err_resp.trim()),
+                    ));
+                }
+                Err(e) => last_err = Some(e),
+            },
+            Err(e) => last_err = Some(e),
+        }
+
+        if attempt < retries {
+            println!(
+                "Retrying '{}' ({}/{})",
+                cmd,
+                attempt + 1,
+                retries
+            );
+        }
+    }
+
+    Err(last_err
+        .unwrap_or_else(|| io::Error::new(io::ErrorKind::Other, "query_with_retry failed with no recorded error")))
+}
+
+fn is_no_error(resp: &str) -> bool {
+    let trimmed = resp.trim();
+    trimmed.starts_with("0,") || trimmed == "0" || trimmed.to_ascii_lowercase().contains("no error")
+}
@@ -0,0 +1,126 @@
+use std::io::{self, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// One measured sweep point, pushed to telemetry clients as a
+/// newline-delimited JSON record.
+#[derive(Debug, Clone, Copy)]
+pub struct TelemetryRecord {
+    pub current_ma: f64,
+    pub peak_wl_nm: f64,
+    pub peak_power_dbm: f64,
+}
+
+impl TelemetryRecord {
+    fn to_json_line(&self, interval_ms: u64) -> String {
+        let timestamp_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis())
+            .unwrap_or(0);
+
+        format!(
+            "{{\"current_ma\":{:.4},\"peak_wl_nm\":{:.4},\"peak_power_dbm\":{:.4},\"timestamp\":{},\"interval\":{}}}\n",
+            self.current_ma, self.peak_wl_nm, self.peak_power_dbm, timestamp_ms, interval_ms
+        )
+    }
+}
+
+/// Non-blocking TCP broadcaster for sweep telemetry. A client connects and
+/// receives one JSON record per measured point, plus a repeat of the most
+/// recent record whenever `tick` is called after `interval` has elapsed
+/// since the last frame. Frames are only sent from `publish` and `tick`, so
+/// no frames go out while the caller is busy between those calls. Every
+/// frame carries `interval` in milliseconds. If no client is connected, or
+/// a connected client can't keep up, publishing is a no-op rather than
+/// something that can stall the instrument I/O.
+pub struct TelemetryServer {
+    listener: TcpListener,
+    clients: Vec<TcpStream>,
+    interval: Duration,
+    last_record: Option<TelemetryRecord>,
+    last_published_at: Instant,
+}
+
+impl TelemetryServer {
+    /// Binds a non-blocking TCP listener on `addr`, with `interval` as the
+    /// minimum time `tick` waits before repeating the last record.
+    pub fn bind<A: ToSocketAddrs>(addr: A, interval: Duration) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        listener.set_nonblocking(true)?;
+
+        Ok(TelemetryServer {
+            listener,
+            clients: Vec::new(),
+            interval,
+            last_record: None,
+            last_published_at: Instant::now(),
+        })
+    }
+
+    fn accept_pending(&mut self) {
+        loop {
+            match self.listener.accept() {
+                Ok((stream, _addr)) => {
+                    if let Err(e) = stream.set_nonblocking(true) {
+                        println!("Warning: failed to configure telemetry client: {}", e);
+                        continue;
+                    }
+                    println!("Telemetry client connected");
+                    self.clients.push(stream);
+                }
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                Err(e) => {
+                    println!("Warning: telemetry accept failed: {}", e);
+                    break;
+                }
+            }
+        }
+    }
+
+    fn broadcast(&mut self, line: &str) {
+        if self.clients.is_empty() {
+            return;
+        }
+
+        let bytes = line.as_bytes();
+        self.clients.retain_mut(|client| match client.write(bytes) {
+            Ok(n) if n == bytes.len() => true,
+            // A partial write has nowhere to stash the unwritten remainder
+            // between calls, so the next publish would be appended directly
+            // onto it and corrupt the newline-delimited framing; drop the
+            // client instead of risking a merged/garbled line.
+            Ok(_) => false,
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => true,
+            Err(_) => false, // client disconnected or wedged, drop it
+        });
+    }
+
+    /// Publishes a freshly measured point to every connected client.
+    pub fn publish(&mut self, record: TelemetryRecord) {
+        self.accept_pending();
+
+        let line = record.to_json_line(self.interval.as_millis() as u64);
+        self.broadcast(&line);
+
+        self.last_record = Some(record);
+        self.last_published_at = Instant::now();
+    }
+
+    /// Called between sweep points. Re-sends the most recent record as a
+    /// status frame once `interval` has elapsed since the last publish, so
+    /// a connected client keeps seeing frames while the instrument dwells
+    /// between measurements.
+    pub fn tick(&mut self) {
+        self.accept_pending();
+
+        if self.last_published_at.elapsed() < self.interval {
+            return;
+        }
+
+        if let Some(record) = self.last_record {
+            let line = record.to_json_line(self.interval.as_millis() as u64);
+            self.broadcast(&line);
+        }
+        self.last_published_at = Instant::now();
+    }
+}